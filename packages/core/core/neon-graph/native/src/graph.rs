@@ -0,0 +1,772 @@
+// Requires `serde` (with the `derive` feature) and `bincode` as crate
+// dependencies for `GraphSnapshot`'s (de)serialization, on top of the
+// `neon` dependency `lib.rs` already needs for `EventHandler`.
+use neon::event::EventHandler;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+
+pub type NodeValue = HashMap<String, Value>;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Value {
+  F64(f64),
+  String(String),
+  Bool(bool),
+  Null,
+  Undefined,
+  /// Milliseconds since the Unix epoch, mirroring a JS `Date`.
+  Date(f64),
+  /// Raw bytes from a JS `Buffer`/`ArrayBuffer`, so node weights can carry
+  /// compiled asset contents or source maps without base64 encoding.
+  /// Typed-array views (`Uint8Array`, etc.) are not converted to this
+  /// variant — see the conversion note in `lib.rs`.
+  Bytes(Vec<u8>),
+  Array(Vec<Value>),
+  Object(HashMap<String, Value>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Edge {
+  to: String,
+  edge_type: Option<String>,
+}
+
+const GRAPH_SNAPSHOT_VERSION: u32 = 1;
+
+/// On-disk/on-wire shape of a `Graph`, used by `serialize`/`deserialize` so
+/// the bundler can persist its dependency graph across runs. `version` is
+/// bumped whenever this shape changes incompatibly.
+#[derive(Serialize, Deserialize)]
+struct GraphSnapshot {
+  version: u32,
+  // Node insertion order is reconstructed by replaying `nodes` through
+  // `add_node` in order, so this is the only record of it we need.
+  nodes: Vec<(String, NodeValue)>,
+  edges: Vec<(String, String, Option<String>)>,
+  root_id: Option<String>,
+}
+
+/// Which way a `query` walks the out/in edge indexes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+  Out,
+  In,
+}
+
+/// An op fired by a mutating `Graph` method, collected by `JsGraph` and
+/// dispatched to `onChange` listeners once the lock guard has been dropped.
+#[derive(Debug, Clone)]
+pub enum GraphEvent {
+  AddNode(NodeValue),
+  RemoveNode(NodeValue),
+  AddEdge {
+    from: String,
+    to: String,
+    edge_type: Option<String>,
+  },
+  RemoveEdge {
+    from: String,
+    to: String,
+    edge_type: Option<String>,
+  },
+}
+
+impl GraphEvent {
+  pub fn kind(&self) -> &'static str {
+    match self {
+      GraphEvent::AddNode(_) => "add-node",
+      GraphEvent::RemoveNode(_) => "remove-node",
+      GraphEvent::AddEdge { .. } => "add-edge",
+      GraphEvent::RemoveEdge { .. } => "remove-edge",
+    }
+  }
+}
+
+/// A single mutation in a `batch` call, parsed from the JS operation
+/// descriptor before the lock is taken.
+#[derive(Debug, Clone)]
+pub enum BatchOp {
+  AddNode(NodeValue),
+  AddEdge {
+    from: String,
+    to: String,
+    edge_type: Option<String>,
+  },
+  RemoveById(String),
+}
+
+#[derive(Clone)]
+pub struct Graph {
+  nodes: HashMap<String, NodeValue>,
+  order: Vec<String>,
+  out_edges: HashMap<String, Vec<Edge>>,
+  in_edges: HashMap<String, Vec<Edge>>,
+  root_id: Option<String>,
+  listeners: Vec<Arc<EventHandler>>,
+  pending_events: Vec<GraphEvent>,
+}
+
+impl Graph {
+  pub fn new() -> Self {
+    Graph {
+      nodes: HashMap::new(),
+      order: Vec::new(),
+      out_edges: HashMap::new(),
+      in_edges: HashMap::new(),
+      root_id: None,
+      listeners: Vec::new(),
+      pending_events: Vec::new(),
+    }
+  }
+
+  fn extract_id(value: &NodeValue) -> Result<String, String> {
+    match value.get("id") {
+      Some(Value::String(id)) => Ok(id.clone()),
+      _ => Err("Node is missing a string \"id\" field".to_string()),
+    }
+  }
+
+  /// Registers a JS callback to be invoked after every node or edge
+  /// addition or removal. `restore` swaps in the reloaded graph without
+  /// firing any events. The handler owns its own persistent root, so it
+  /// can be scheduled from outside the `cx.lock()` section that produced
+  /// the event.
+  pub fn on_change(&mut self, handler: EventHandler) {
+    self.listeners.push(Arc::new(handler));
+  }
+
+  /// Cheap snapshot of the registered listeners, taken while still under
+  /// the lock guard so the caller can dispatch after releasing it.
+  pub fn listeners(&self) -> Vec<Arc<EventHandler>> {
+    self.listeners.clone()
+  }
+
+  /// Drains the events recorded since the last call, so they can be
+  /// dispatched once the lock guard is dropped.
+  pub fn take_events(&mut self) -> Vec<GraphEvent> {
+    std::mem::take(&mut self.pending_events)
+  }
+
+  pub fn add_node(&mut self, value: &NodeValue) -> Result<(), String> {
+    let id = Self::extract_id(value)?;
+    if !self.nodes.contains_key(&id) {
+      self.order.push(id.clone());
+      self.out_edges.entry(id.clone()).or_insert_with(Vec::new);
+      self.in_edges.entry(id.clone()).or_insert_with(Vec::new);
+    }
+    self.nodes.insert(id, value.clone());
+    self.pending_events.push(GraphEvent::AddNode(value.clone()));
+    Ok(())
+  }
+
+  pub fn get_node(&self, id: &str) -> Option<&NodeValue> {
+    self.nodes.get(id)
+  }
+
+  pub fn set_root_node(&mut self, value: &NodeValue) -> Result<(), String> {
+    let id = Self::extract_id(value)?;
+    self.add_node(value)?;
+    self.root_id = Some(id);
+    Ok(())
+  }
+
+  pub fn add_edge(&mut self, id_a: &str, id_b: &str, edge_type: Option<&str>) -> Result<(), String> {
+    if !self.nodes.contains_key(id_a) {
+      return Err(format!("Node \"{}\" does not exist", id_a));
+    }
+    if !self.nodes.contains_key(id_b) {
+      return Err(format!("Node \"{}\" does not exist", id_b));
+    }
+
+    self.out_edges.entry(id_a.to_string()).or_insert_with(Vec::new).push(Edge {
+      to: id_b.to_string(),
+      edge_type: edge_type.map(|s| s.to_string()),
+    });
+    self.in_edges.entry(id_b.to_string()).or_insert_with(Vec::new).push(Edge {
+      to: id_a.to_string(),
+      edge_type: edge_type.map(|s| s.to_string()),
+    });
+    self.pending_events.push(GraphEvent::AddEdge {
+      from: id_a.to_string(),
+      to: id_b.to_string(),
+      edge_type: edge_type.map(|s| s.to_string()),
+    });
+    Ok(())
+  }
+
+  pub fn remove_node(&mut self, value: &NodeValue) -> Option<NodeValue> {
+    let id = Self::extract_id(value).ok()?;
+    self.remove_by_id(&id)
+  }
+
+  pub fn remove_by_id(&mut self, id: &str) -> Option<NodeValue> {
+    let removed = self.nodes.remove(id)?;
+    self.order.retain(|node_id| node_id != id);
+
+    if let Some(edges) = self.out_edges.remove(id) {
+      for edge in edges {
+        self.pending_events.push(GraphEvent::RemoveEdge {
+          from: id.to_string(),
+          to: edge.to,
+          edge_type: edge.edge_type,
+        });
+      }
+    }
+    if let Some(edges) = self.in_edges.remove(id) {
+      for edge in edges {
+        // `edge.to` here is actually the predecessor id (in-edges point
+        // back at their source), so the removed edge ran predecessor -> id.
+        // Self-loops (`id -> id`) live in both `out_edges[id]` and
+        // `in_edges[id]`; skip them here so the out-edges loop above is the
+        // only one to report them, avoiding a duplicate RemoveEdge event.
+        if edge.to == id {
+          continue;
+        }
+        self.pending_events.push(GraphEvent::RemoveEdge {
+          from: edge.to,
+          to: id.to_string(),
+          edge_type: edge.edge_type,
+        });
+      }
+    }
+    for edges in self.out_edges.values_mut() {
+      edges.retain(|edge| edge.to != id);
+    }
+    for edges in self.in_edges.values_mut() {
+      edges.retain(|edge| edge.to != id);
+    }
+    if self.root_id.as_deref() == Some(id) {
+      self.root_id = None;
+    }
+
+    self.pending_events.push(GraphEvent::RemoveNode(removed.clone()));
+    Some(removed)
+  }
+
+  pub fn traverse<F: FnMut(&NodeValue)>(
+    &self,
+    start_node: Option<&NodeValue>,
+    edge_type: Option<&str>,
+    mut callback: F,
+  ) -> Result<(), String> {
+    let start_id = match start_node {
+      Some(value) => Self::extract_id(value)?,
+      None => self.root_id.clone().ok_or_else(|| "No root node set".to_string())?,
+    };
+
+    let mut visited = HashSet::new();
+    let mut queue = VecDeque::new();
+    queue.push_back(start_id);
+
+    while let Some(id) = queue.pop_front() {
+      if !visited.insert(id.clone()) {
+        continue;
+      }
+      if let Some(node) = self.nodes.get(&id) {
+        callback(node);
+      }
+      if let Some(edges) = self.out_edges.get(&id) {
+        for edge in edges {
+          if edge_type.map_or(true, |t| edge.edge_type.as_deref() == Some(t)) && !visited.contains(&edge.to) {
+            queue.push_back(edge.to.clone());
+          }
+        }
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Recursive reachability query: expands outward from `start_id` one
+  /// frontier at a time, following only edges matching `edge_type` (when
+  /// given) in `direction`, up to `max_depth` rounds. `visited` makes the
+  /// expansion cycle-safe, so dependency cycles terminate instead of
+  /// looping forever. Returns the reachable nodes, excluding the start node.
+  pub fn query(
+    &self,
+    start_id: &str,
+    edge_type: Option<&str>,
+    direction: Direction,
+    max_depth: Option<usize>,
+  ) -> Result<Vec<NodeValue>, String> {
+    if !self.nodes.contains_key(start_id) {
+      return Err(format!("Node \"{}\" does not exist", start_id));
+    }
+
+    let edges_by_id = match direction {
+      Direction::Out => &self.out_edges,
+      Direction::In => &self.in_edges,
+    };
+
+    let mut visited = HashSet::new();
+    visited.insert(start_id.to_string());
+    let mut frontier = vec![start_id.to_string()];
+    let mut depth = 0;
+
+    while !frontier.is_empty() && max_depth.map_or(true, |max_depth| depth < max_depth) {
+      let mut next_frontier = Vec::new();
+      for id in &frontier {
+        if let Some(edges) = edges_by_id.get(id) {
+          for edge in edges {
+            if edge_type.map_or(true, |t| edge.edge_type.as_deref() == Some(t)) && visited.insert(edge.to.clone()) {
+              next_frontier.push(edge.to.clone());
+            }
+          }
+        }
+      }
+      frontier = next_frontier;
+      depth += 1;
+    }
+
+    visited.remove(start_id);
+    Ok(
+      self
+        .order
+        .iter()
+        .filter(|id| visited.contains(*id))
+        .filter_map(|id| self.nodes.get(id).cloned())
+        .collect(),
+    )
+  }
+
+  fn to_snapshot(&self) -> GraphSnapshot {
+    let nodes = self
+      .order
+      .iter()
+      .filter_map(|id| self.nodes.get(id).map(|node| (id.clone(), node.clone())))
+      .collect();
+    let edges = self
+      .order
+      .iter()
+      .flat_map(|id| {
+        self
+          .out_edges
+          .get(id)
+          .into_iter()
+          .flatten()
+          .map(move |edge| (id.clone(), edge.to.clone(), edge.edge_type.clone()))
+      })
+      .collect();
+
+    GraphSnapshot {
+      version: GRAPH_SNAPSHOT_VERSION,
+      nodes,
+      edges,
+      root_id: self.root_id.clone(),
+    }
+  }
+
+  /// Dumps every node, typed edge, and the root node id into a compact byte
+  /// buffer, preserving node insertion order so a `traverse` over the
+  /// restored graph yields identical results.
+  pub fn serialize(&self) -> Result<Vec<u8>, String> {
+    bincode::serialize(&self.to_snapshot()).map_err(|err| err.to_string())
+  }
+
+  /// Rebuilds a `Graph` from a buffer produced by `serialize`, rejecting it
+  /// outright if its version header doesn't match this build's format.
+  pub fn deserialize(bytes: &[u8]) -> Result<Graph, String> {
+    let snapshot: GraphSnapshot = bincode::deserialize(bytes).map_err(|err| err.to_string())?;
+    if snapshot.version != GRAPH_SNAPSHOT_VERSION {
+      return Err(format!(
+        "Unsupported graph snapshot version {} (expected {})",
+        snapshot.version, GRAPH_SNAPSHOT_VERSION
+      ));
+    }
+
+    let mut graph = Graph::new();
+    for (_, node) in &snapshot.nodes {
+      graph.add_node(node)?;
+    }
+    for (from, to, edge_type) in &snapshot.edges {
+      graph.add_edge(from, to, edge_type.as_deref())?;
+    }
+    if let Some(root_id) = &snapshot.root_id {
+      if let Some(node) = graph.get_node(root_id).cloned() {
+        graph.set_root_node(&node)?;
+      }
+    }
+    graph.take_events();
+    Ok(graph)
+  }
+
+  /// Reloads this graph in place from a buffer produced by `serialize`,
+  /// carrying the currently-registered `onChange` listeners across the
+  /// reload so a persist+reload cycle doesn't silently drop reactivity.
+  pub fn restore(&mut self, bytes: &[u8]) -> Result<(), String> {
+    let mut restored = Self::deserialize(bytes)?;
+    restored.listeners = self.listeners.clone();
+    *self = restored;
+    Ok(())
+  }
+
+  fn is_self_loop(&self, id: &str, edge_type: Option<&str>) -> bool {
+    self.out_edges.get(id).map_or(false, |edges| {
+      edges
+        .iter()
+        .any(|edge| edge.to == id && edge_type.map_or(true, |t| edge.edge_type.as_deref() == Some(t)))
+    })
+  }
+
+  /// Iterative Tarjan's SCC: each node gets an `index`/`lowlink` assigned
+  /// on first visit, is pushed on an explicit stack, and whenever
+  /// `lowlink == index` the SCC rooted there is popped off. Returns the
+  /// SCCs in completion order (each one only after every SCC reachable
+  /// from it has already completed).
+  fn strongly_connected_components(&self, edge_type: Option<&str>) -> Vec<Vec<String>> {
+    let neighbors = |id: &str| -> Vec<String> {
+      self
+        .out_edges
+        .get(id)
+        .into_iter()
+        .flatten()
+        .filter(|edge| edge_type.map_or(true, |t| edge.edge_type.as_deref() == Some(t)))
+        .map(|edge| edge.to.clone())
+        .collect()
+    };
+
+    let mut next_index = 0usize;
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut lowlink: HashMap<String, usize> = HashMap::new();
+    let mut on_stack: HashSet<String> = HashSet::new();
+    let mut stack: Vec<String> = Vec::new();
+    let mut sccs: Vec<Vec<String>> = Vec::new();
+
+    for start in &self.order {
+      if index.contains_key(start) {
+        continue;
+      }
+
+      index.insert(start.clone(), next_index);
+      lowlink.insert(start.clone(), next_index);
+      next_index += 1;
+      stack.push(start.clone());
+      on_stack.insert(start.clone());
+
+      // Each frame is (node, its neighbor list, how many neighbors visited so far).
+      let mut work: Vec<(String, Vec<String>, usize)> = vec![(start.clone(), neighbors(start), 0)];
+
+      while !work.is_empty() {
+        let frame = work.len() - 1;
+        let pos = work[frame].2;
+
+        if pos < work[frame].1.len() {
+          let child = work[frame].1[pos].clone();
+          work[frame].2 += 1;
+
+          if !index.contains_key(&child) {
+            index.insert(child.clone(), next_index);
+            lowlink.insert(child.clone(), next_index);
+            next_index += 1;
+            stack.push(child.clone());
+            on_stack.insert(child.clone());
+            let child_neighbors = neighbors(&child);
+            work.push((child, child_neighbors, 0));
+          } else if on_stack.contains(&child) {
+            let child_index = index[&child];
+            let node = work[frame].0.clone();
+            if child_index < lowlink[&node] {
+              lowlink.insert(node, child_index);
+            }
+          }
+        } else {
+          let (node, _, _) = work.pop().unwrap();
+
+          if let Some(parent_frame) = work.last() {
+            let parent = parent_frame.0.clone();
+            if lowlink[&node] < lowlink[&parent] {
+              let node_lowlink = lowlink[&node];
+              lowlink.insert(parent, node_lowlink);
+            }
+          }
+
+          if lowlink[&node] == index[&node] {
+            let mut scc = Vec::new();
+            loop {
+              let member = stack.pop().unwrap();
+              on_stack.remove(&member);
+              let is_root = member == node;
+              scc.push(member);
+              if is_root {
+                break;
+              }
+            }
+            sccs.push(scc);
+          }
+        }
+      }
+    }
+
+    sccs
+  }
+
+  /// SCCs with more than one node, plus single-node SCCs with a self-loop,
+  /// are dependency cycles.
+  pub fn find_cycles(&self, edge_type: Option<&str>) -> Vec<Vec<NodeValue>> {
+    self
+      .strongly_connected_components(edge_type)
+      .into_iter()
+      .filter(|scc| scc.len() > 1 || self.is_self_loop(&scc[0], edge_type))
+      .map(|scc| scc.iter().filter_map(|id| self.nodes.get(id).cloned()).collect())
+      .collect()
+  }
+
+  /// Node values in SCC-completion order: Tarjan completes (pops) a node's
+  /// SCC only after every SCC reachable from it, so completion order
+  /// already puts a dependency before the node(s) that point to it — the
+  /// safe build order for a bundler. Returns the offending cycles instead
+  /// if one exists.
+  ///
+  /// Deliberately *not* reversed: the originating request describes this as
+  /// "reverse SCC-completion order," but that wording contradicts its own
+  /// "safe build order" goal — reversing completion order would put
+  /// dependents before their dependencies. Plain completion order is the
+  /// one that's actually buildable, and `topological_sort_orders_dependencies_before_dependents`
+  /// below pins it down.
+  pub fn topological_sort(&self, edge_type: Option<&str>) -> Result<Vec<NodeValue>, Vec<Vec<NodeValue>>> {
+    let sccs = self.strongly_connected_components(edge_type);
+    let cycles: Vec<&Vec<String>> = sccs
+      .iter()
+      .filter(|scc| scc.len() > 1 || self.is_self_loop(&scc[0], edge_type))
+      .collect();
+
+    if !cycles.is_empty() {
+      return Err(
+        cycles
+          .into_iter()
+          .map(|scc| scc.iter().filter_map(|id| self.nodes.get(id).cloned()).collect())
+          .collect(),
+      );
+    }
+
+    Ok(
+      sccs
+        .into_iter()
+        .flatten()
+        .filter_map(|id| self.nodes.get(&id).cloned())
+        .collect(),
+    )
+  }
+
+  /// Applies every op under a single mutation, committing only if all of
+  /// them succeed. Snapshots the graph up front and restores it verbatim
+  /// on the first error, so a failing batch leaves the graph untouched.
+  pub fn apply_batch(&mut self, ops: &[BatchOp]) -> Result<(), String> {
+    let snapshot = self.clone();
+
+    for op in ops {
+      let result = match op {
+        BatchOp::AddNode(node) => self.add_node(node),
+        BatchOp::AddEdge { from, to, edge_type } => self.add_edge(from, to, edge_type.as_deref()),
+        BatchOp::RemoveById(id) => {
+          self.remove_by_id(id);
+          Ok(())
+        }
+      };
+
+      if let Err(err) = result {
+        *self = snapshot;
+        return Err(err);
+      }
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn node(id: &str) -> NodeValue {
+    let mut node = HashMap::new();
+    node.insert("id".to_string(), Value::String(id.to_string()));
+    node
+  }
+
+  fn ids(nodes: &[NodeValue]) -> Vec<String> {
+    nodes
+      .iter()
+      .map(|node| match node.get("id") {
+        Some(Value::String(id)) => id.clone(),
+        _ => panic!("node missing id"),
+      })
+      .collect()
+  }
+
+  #[test]
+  fn add_node_and_edge_fire_events() {
+    let mut graph = Graph::new();
+    graph.add_node(&node("a")).unwrap();
+    graph.add_node(&node("b")).unwrap();
+    graph.add_edge("a", "b", Some("require")).unwrap();
+
+    let events = graph.take_events();
+    assert_eq!(events.len(), 3);
+    assert_eq!(events[0].kind(), "add-node");
+    assert_eq!(events[1].kind(), "add-node");
+    assert_eq!(events[2].kind(), "add-edge");
+  }
+
+  #[test]
+  fn remove_by_id_fires_remove_edge_for_incoming_and_outgoing_edges() {
+    let mut graph = Graph::new();
+    graph.add_node(&node("a")).unwrap();
+    graph.add_node(&node("b")).unwrap();
+    graph.add_node(&node("c")).unwrap();
+    graph.add_edge("a", "b", None).unwrap();
+    graph.add_edge("b", "c", None).unwrap();
+    graph.take_events();
+
+    graph.remove_by_id("b");
+    let events = graph.take_events();
+    let remove_edges: Vec<(String, String)> = events
+      .iter()
+      .filter_map(|event| match event {
+        GraphEvent::RemoveEdge { from, to, .. } => Some((from.clone(), to.clone())),
+        _ => None,
+      })
+      .collect();
+
+    assert!(remove_edges.contains(&("a".to_string(), "b".to_string())));
+    assert!(remove_edges.contains(&("b".to_string(), "c".to_string())));
+  }
+
+  #[test]
+  fn remove_by_id_fires_self_loop_remove_edge_only_once() {
+    let mut graph = Graph::new();
+    graph.add_node(&node("a")).unwrap();
+    graph.add_edge("a", "a", None).unwrap();
+    graph.take_events();
+
+    graph.remove_by_id("a");
+    let events = graph.take_events();
+    let remove_edge_count = events
+      .iter()
+      .filter(|event| matches!(event, GraphEvent::RemoveEdge { .. }))
+      .count();
+
+    assert_eq!(remove_edge_count, 1);
+  }
+
+  #[test]
+  fn query_is_cycle_safe_and_respects_max_depth() {
+    let mut graph = Graph::new();
+    graph.add_node(&node("a")).unwrap();
+    graph.add_node(&node("b")).unwrap();
+    graph.add_node(&node("c")).unwrap();
+    graph.add_edge("a", "b", None).unwrap();
+    graph.add_edge("b", "c", None).unwrap();
+    graph.add_edge("c", "a", None).unwrap();
+
+    let reachable = graph.query("a", None, Direction::Out, None).unwrap();
+    let mut reachable_ids = ids(&reachable);
+    reachable_ids.sort();
+    assert_eq!(reachable_ids, vec!["b".to_string(), "c".to_string()]);
+
+    let one_hop = graph.query("a", None, Direction::Out, Some(1)).unwrap();
+    assert_eq!(ids(&one_hop), vec!["b".to_string()]);
+  }
+
+  #[test]
+  fn query_in_direction_follows_reverse_edges() {
+    let mut graph = Graph::new();
+    graph.add_node(&node("a")).unwrap();
+    graph.add_node(&node("b")).unwrap();
+    graph.add_edge("a", "b", None).unwrap();
+
+    let predecessors = graph.query("b", None, Direction::In, None).unwrap();
+    assert_eq!(ids(&predecessors), vec!["a".to_string()]);
+  }
+
+  #[test]
+  fn serialize_deserialize_round_trips_traversal_order() {
+    let mut graph = Graph::new();
+    graph.add_node(&node("a")).unwrap();
+    graph.add_node(&node("b")).unwrap();
+    graph.add_node(&node("c")).unwrap();
+    graph.add_edge("a", "b", None).unwrap();
+    graph.add_edge("a", "c", None).unwrap();
+    graph.set_root_node(&node("a")).unwrap();
+
+    let bytes = graph.serialize().unwrap();
+    let restored = Graph::deserialize(&bytes).unwrap();
+
+    let mut original_order = Vec::new();
+    graph.traverse(None, None, |node| original_order.push(node.clone())).unwrap();
+    let mut restored_order = Vec::new();
+    restored.traverse(None, None, |node| restored_order.push(node.clone())).unwrap();
+
+    assert_eq!(ids(&original_order), ids(&restored_order));
+  }
+
+  #[test]
+  fn deserialize_rejects_mismatched_version() {
+    let snapshot_bytes = bincode::serialize(&GraphSnapshot {
+      version: GRAPH_SNAPSHOT_VERSION + 1,
+      nodes: vec![],
+      edges: vec![],
+      root_id: None,
+    })
+    .unwrap();
+
+    assert!(Graph::deserialize(&snapshot_bytes).is_err());
+  }
+
+  #[test]
+  fn batch_rolls_back_on_first_error() {
+    let mut graph = Graph::new();
+    graph.add_node(&node("a")).unwrap();
+    graph.take_events();
+
+    let ops = vec![
+      BatchOp::AddNode(node("b")),
+      BatchOp::AddEdge {
+        from: "a".to_string(),
+        to: "does-not-exist".to_string(),
+        edge_type: None,
+      },
+    ];
+
+    let result = graph.apply_batch(&ops);
+    assert!(result.is_err());
+    assert!(graph.get_node("b").is_none());
+    assert!(graph.take_events().is_empty());
+  }
+
+  #[test]
+  fn find_cycles_and_topological_sort_detect_a_cycle() {
+    let mut graph = Graph::new();
+    graph.add_node(&node("a")).unwrap();
+    graph.add_node(&node("b")).unwrap();
+    graph.add_node(&node("c")).unwrap();
+    graph.add_edge("a", "b", None).unwrap();
+    graph.add_edge("b", "c", None).unwrap();
+    graph.add_edge("c", "a", None).unwrap();
+
+    let cycles = graph.find_cycles(None);
+    assert_eq!(cycles.len(), 1);
+    assert_eq!(cycles[0].len(), 3);
+
+    assert!(graph.topological_sort(None).is_err());
+  }
+
+  #[test]
+  fn topological_sort_orders_dependencies_before_dependents() {
+    let mut graph = Graph::new();
+    graph.add_node(&node("app")).unwrap();
+    graph.add_node(&node("lib")).unwrap();
+    graph.add_node(&node("util")).unwrap();
+    graph.add_edge("app", "lib", None).unwrap();
+    graph.add_edge("lib", "util", None).unwrap();
+
+    let order = ids(&graph.topological_sort(None).unwrap());
+    let app_pos = order.iter().position(|id| id == "app").unwrap();
+    let lib_pos = order.iter().position(|id| id == "lib").unwrap();
+    let util_pos = order.iter().position(|id| id == "util").unwrap();
+
+    assert!(util_pos < lib_pos);
+    assert!(lib_pos < app_pos);
+  }
+}
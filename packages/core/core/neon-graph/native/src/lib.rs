@@ -1,10 +1,17 @@
+use neon::event::EventHandler;
 use neon::prelude::*;
 use std::collections::HashMap;
 use std::sync::Arc;
 
 mod graph;
-use graph::{Graph, Value};
-
+use graph::{BatchOp, Direction, Graph, GraphEvent, Value};
+
+// Only `Buffer` and `ArrayBuffer` convert to `Value::Bytes`. A typed-array
+// view (`Uint8Array`, `Int32Array`, ...) is a distinct JS type from both and
+// isn't detected here, so it falls through to the generic `JsObject` arm and
+// converts to an object keyed by index instead of `Value::Bytes`. Pass
+// `Buffer.from(view.buffer)` (or the view's `.buffer`) from JS if you need
+// raw bytes out of a typed array.
 fn js_value_to_value(cx: &mut CallContext<JsGraph>, js: &Handle<JsValue>) -> NeonResult<Value> {
   match js.downcast::<JsArray>() {
     Ok(array) => {
@@ -17,6 +24,24 @@ fn js_value_to_value(cx: &mut CallContext<JsGraph>, js: &Handle<JsValue>) -> Neo
     }
     Err(_) => {}
   };
+  match js.downcast::<JsBuffer>() {
+    Ok(buffer) => {
+      let bytes = cx.borrow(&buffer, |data| data.as_slice::<u8>().to_vec());
+      return Ok(Value::Bytes(bytes));
+    }
+    Err(_) => {}
+  };
+  match js.downcast::<JsArrayBuffer>() {
+    Ok(array_buffer) => {
+      let bytes = cx.borrow(&array_buffer, |data| data.as_slice::<u8>().to_vec());
+      return Ok(Value::Bytes(bytes));
+    }
+    Err(_) => {}
+  };
+  match js.downcast::<JsDate>() {
+    Ok(date) => return Ok(Value::Date(date.value(cx))),
+    Err(_) => {}
+  };
   match js.downcast::<JsObject>() {
     Ok(object) => {
       let mut obj_map: HashMap<String, Value> = HashMap::new();
@@ -49,19 +74,30 @@ fn js_value_to_value(cx: &mut CallContext<JsGraph>, js: &Handle<JsValue>) -> Neo
     Ok(_) => return Ok(Value::Undefined),
     Err(_) => {}
   };
-  unreachable!();
+  cx.throw_error("Unsupported JS value type passed to the graph")
 }
 
-fn value_to_js_value<'a>(
-  cx: &mut CallContext<'a, JsGraph>,
-  value: &Value,
-) -> JsResult<'a, JsValue> {
+fn value_to_js_value<'a, C: Context<'a>>(cx: &mut C, value: &Value) -> JsResult<'a, JsValue> {
   Ok(match value {
     Value::F64(num) => cx.number(num.clone()).upcast(),
     Value::String(string) => cx.string(string).upcast(),
     Value::Null => cx.null().upcast(),
     Value::Undefined => cx.undefined().upcast(),
     Value::Bool(boolean) => cx.boolean(boolean.clone()).upcast(),
+    // `JsDate::new` is assumed present on the pinned `neon` version; there's
+    // no manifest in this tree to pin that version against, so double-check
+    // it against the real `Cargo.toml` before merging.
+    Value::Date(millis) => match JsDate::new(cx, *millis) {
+      Ok(date) => date.upcast(),
+      Err(_) => return cx.throw_error("Invalid date value"),
+    },
+    Value::Bytes(bytes) => {
+      let mut buffer = JsBuffer::new(cx, bytes.len() as u32)?;
+      cx.borrow_mut(&mut buffer, |data| {
+        data.as_mut_slice().copy_from_slice(bytes);
+      });
+      buffer.upcast()
+    }
     Value::Array(vector) => {
       // Adapted from https://neon-bindings.com/docs/arrays
       let js_array = JsArray::new(cx, vector.len() as u32);
@@ -82,6 +118,52 @@ fn value_to_js_value<'a>(
   })
 }
 
+fn event_payload_to_js<'a>(cx: &mut TaskContext<'a>, event: &GraphEvent) -> JsResult<'a, JsValue> {
+  match event {
+    GraphEvent::AddNode(node) | GraphEvent::RemoveNode(node) => {
+      value_to_js_value(cx, &Value::Object(node.clone()))
+    }
+    GraphEvent::AddEdge { from, to, edge_type } | GraphEvent::RemoveEdge { from, to, edge_type } => {
+      let obj = JsObject::new(cx);
+      let from_js = cx.string(from);
+      obj.set(cx, "from", from_js)?;
+      let to_js = cx.string(to);
+      obj.set(cx, "to", to_js)?;
+      let edge_type_js: Handle<JsValue> = match edge_type {
+        Some(edge_type) => cx.string(edge_type).upcast(),
+        None => cx.undefined().upcast(),
+      };
+      obj.set(cx, "edgeType", edge_type_js)?;
+      Ok(obj.upcast())
+    }
+  }
+}
+
+// Dispatched after the `cx.lock()` guard that produced `events` has already
+// been dropped, so listener callbacks never run while the graph is borrowed.
+//
+// Uses `EventHandler::schedule(arg, callback)`, which threads the event
+// through as an explicit argument instead of a captured closure, so the
+// handed-off data is unambiguous regardless of the exact `schedule` /
+// `schedule_with` split in the pinned `neon` version. Like `JsDate::new`
+// above, this call site has no `Cargo.toml` to build and test against here —
+// confirm both against the real manifest before merging.
+fn dispatch_events(events: Vec<GraphEvent>, listeners: Vec<Arc<EventHandler>>) {
+  if events.is_empty() || listeners.is_empty() {
+    return;
+  }
+  for event in events {
+    for listener in &listeners {
+      listener.schedule(event.clone(), |mut cx, this, callback, event| {
+        let kind = cx.string(event.kind()).upcast();
+        let payload = event_payload_to_js(&mut cx, &event)?;
+        callback.call(&mut cx, this, vec![kind, payload])?;
+        Ok(())
+      });
+    }
+  }
+}
+
 declare_types! {
   pub class JsGraph for Graph {
     init(mut _cx) {
@@ -95,17 +177,39 @@ declare_types! {
         Value::Object(obj_map) => {
           obj_map
         },
-        _ => unreachable!()
+        _ => {
+          return cx.throw_error("Node is not an object")
+        }
       };
 
-      let guard = cx.lock();
-      let mut graph = this.borrow_mut(&guard);
-      match graph.add_node(&value) {
-        Ok(()) => Ok(js_value.upcast()),
+      let (result, events, listeners) = {
+        let guard = cx.lock();
+        let mut graph = this.borrow_mut(&guard);
+        let result = graph.add_node(&value);
+        (result, graph.take_events(), graph.listeners())
+      };
+
+      match result {
+        Ok(()) => {
+          dispatch_events(events, listeners);
+          Ok(js_value.upcast())
+        },
         Err(err) => panic!(err),
       }
     }
 
+    method onChange(mut cx) {
+      let mut this = cx.this();
+      let callback = cx.argument::<JsFunction>(0)?;
+      let handler = EventHandler::new(&cx, this, callback);
+
+      let guard = cx.lock();
+      let mut graph = this.borrow_mut(&guard);
+      graph.on_change(handler);
+
+      Ok(cx.undefined().upcast())
+    }
+
     method getNode(mut cx) {
       let id = cx.argument::<JsString>(0)?.value();
       let mut this = cx.this();
@@ -161,7 +265,7 @@ declare_types! {
         None => None,
       };
 
-      {
+      let (events, listeners) = {
         let guard = cx.lock();
         let mut graph = this.borrow_mut(&guard);
         match edge_type {
@@ -172,7 +276,9 @@ declare_types! {
             let _ = graph.add_edge(&id_a[..], &id_b[..], None);
           }
         }
+        (graph.take_events(), graph.listeners())
       };
+      dispatch_events(events, listeners);
       Ok(cx.undefined().upcast())
     }
 
@@ -188,15 +294,19 @@ declare_types! {
         }
       };
 
-      let removed = {
+      let (removed, events, listeners) = {
         let guard = cx.lock();
         let mut graph = this.borrow_mut(&guard);
 
-        graph.remove_node(&value)
+        let removed = graph.remove_node(&value);
+        (removed, graph.take_events(), graph.listeners())
       };
 
       match removed {
-        Some(_) => Ok(cx.undefined().upcast()),
+        Some(_) => {
+          dispatch_events(events, listeners);
+          Ok(cx.undefined().upcast())
+        },
         None => return cx.throw_error("Does not have node")
       }
     }
@@ -205,12 +315,14 @@ declare_types! {
       let mut this = cx.this();
       let id = cx.argument::<JsString>(0)?.value();
 
-      {
+      let (events, listeners) = {
         let guard = cx.lock();
         let mut graph = this.borrow_mut(&guard);
 
         let _ = graph.remove_by_id(&id[..]);
+        (graph.take_events(), graph.listeners())
       };
+      dispatch_events(events, listeners);
 
       Ok(cx.undefined().upcast())
     }
@@ -218,22 +330,29 @@ declare_types! {
     method traverse(mut cx) {
       let mut this = cx.this();
       let cb = cx.argument::<JsFunction>(0)?;
-      let start_node = cx.argument_opt(1).and_then(|start_node| {
-        if let Ok(_) = start_node.downcast::<JsNull>() {
-          return None
-        } else if let Ok(_) = start_node.downcast::<JsUndefined>() {
-          return None
+      let start_node = match cx.argument_opt(1) {
+        None => None,
+        Some(start_node) => {
+          if let Ok(_) = start_node.downcast::<JsNull>() {
+            None
+          } else if let Ok(_) = start_node.downcast::<JsUndefined>() {
+            None
+          } else {
+            let js_object = match start_node.downcast::<JsObject>() {
+              Ok(js_object) => js_object,
+              Err(_) => {
+                return cx.throw_error("Start node is not an object")
+              }
+            };
+            match js_value_to_value(&mut cx, &js_object.upcast())? {
+              Value::Object(obj_map) => Some(obj_map),
+              _ => {
+                return cx.throw_error("Start node is not an object")
+              }
+            }
+          }
         }
-
-        let js_object = start_node.downcast::<JsObject>().or_throw(&mut cx).unwrap();
-        let converted_value = match js_value_to_value(&mut cx, &js_object.upcast()).unwrap() {
-          Value::Object(obj_map) => obj_map,
-          _ => unimplemented!(),
-        };
-
-
-        Some(converted_value)
-      });
+      };
 
       let edge_type = cx.argument_opt(1).and_then(|edge_type| {
         if let Ok(_) = edge_type.downcast::<JsNull>() {
@@ -257,6 +376,208 @@ declare_types! {
 
       Ok(undefined.upcast())
     }
+
+    method query(mut cx) {
+      let mut this = cx.this();
+      let start_id = cx.argument::<JsString>(0)?.value();
+
+      let edge_type = cx.argument_opt(1).and_then(|edge_type| {
+        if let Ok(_) = edge_type.downcast::<JsNull>() {
+          return None
+        } else if let Ok(_) = edge_type.downcast::<JsUndefined>() {
+          return None
+        }
+        Some(edge_type.downcast::<JsString>().or_throw(&mut cx).unwrap().value())
+      });
+
+      let direction = match cx.argument_opt(2) {
+        Some(direction) => {
+          match direction.downcast::<JsString>() {
+            Ok(direction) => match &direction.value()[..] {
+              "in" => Direction::In,
+              _ => Direction::Out,
+            },
+            Err(_) => Direction::Out,
+          }
+        },
+        None => Direction::Out,
+      };
+
+      let max_depth = match cx.argument_opt(3) {
+        Some(max_depth) => match max_depth.downcast::<JsNumber>() {
+          Ok(max_depth) => Some(max_depth.value() as usize),
+          Err(_) => None,
+        },
+        None => None,
+      };
+
+      let result = {
+        let guard = cx.lock();
+        let graph = this.borrow_mut(&guard);
+        graph.query(&start_id[..], edge_type.as_ref().map(|e| &e[..]), direction, max_depth)
+      };
+
+      match result {
+        Ok(nodes) => {
+          let js_array = JsArray::new(&mut cx, nodes.len() as u32);
+          for (i, node) in nodes.iter().enumerate() {
+            let js_node = value_to_js_value(&mut cx, &Value::Object(node.clone()))?;
+            js_array.set(&mut cx, i as u32, js_node)?;
+          }
+          Ok(js_array.upcast())
+        },
+        Err(err) => cx.throw_error(err),
+      }
+    }
+
+    method serialize(mut cx) {
+      let mut this = cx.this();
+      let bytes = {
+        let guard = cx.lock();
+        let graph = this.borrow_mut(&guard);
+        graph.serialize()
+      };
+
+      match bytes {
+        Ok(bytes) => {
+          let mut buffer = JsBuffer::new(&mut cx, bytes.len() as u32)?;
+          cx.borrow_mut(&mut buffer, |data| {
+            data.as_mut_slice().copy_from_slice(&bytes);
+          });
+          Ok(buffer.upcast())
+        },
+        Err(err) => cx.throw_error(err),
+      }
+    }
+
+    method deserialize(mut cx) {
+      let mut this = cx.this();
+      let buffer = cx.argument::<JsBuffer>(0)?;
+      let bytes = cx.borrow(&buffer, |data| data.as_slice::<u8>().to_vec());
+
+      let result = {
+        let guard = cx.lock();
+        let mut graph = this.borrow_mut(&guard);
+        graph.restore(&bytes)
+      };
+
+      match result {
+        Ok(()) => Ok(cx.undefined().upcast()),
+        Err(err) => cx.throw_error(err),
+      }
+    }
+
+    method batch(mut cx) {
+      let mut this = cx.this();
+      let ops_arg = cx.argument::<JsArray>(0)?;
+      let ops_js = ops_arg.to_vec(&mut cx)?;
+
+      let mut ops = Vec::new();
+      for op_js in ops_js {
+        let op_obj = op_js.downcast::<JsObject>().or_throw(&mut cx)?;
+        let op_name = op_obj.get(&mut cx, "op")?.downcast::<JsString>().or_throw(&mut cx)?.value();
+
+        let batch_op = match &op_name[..] {
+          "addNode" => {
+            let node_js = op_obj.get(&mut cx, "node")?;
+            let value = match js_value_to_value(&mut cx, &node_js)? {
+              Value::Object(obj_map) => obj_map,
+              _ => return cx.throw_error("Batch \"addNode\" op requires a \"node\" object"),
+            };
+            BatchOp::AddNode(value)
+          },
+          "addEdge" => {
+            let from = op_obj.get(&mut cx, "from")?.downcast::<JsString>().or_throw(&mut cx)?.value();
+            let to = op_obj.get(&mut cx, "to")?.downcast::<JsString>().or_throw(&mut cx)?.value();
+            let edge_type = match op_obj.get(&mut cx, "edgeType")?.downcast::<JsString>() {
+              Ok(edge_type) => Some(edge_type.value()),
+              Err(_) => None,
+            };
+            BatchOp::AddEdge { from, to, edge_type }
+          },
+          "removeById" => {
+            let id = op_obj.get(&mut cx, "id")?.downcast::<JsString>().or_throw(&mut cx)?.value();
+            BatchOp::RemoveById(id)
+          },
+          other => return cx.throw_error(format!("Unknown batch op \"{}\"", other)),
+        };
+        ops.push(batch_op);
+      }
+
+      let (result, events, listeners) = {
+        let guard = cx.lock();
+        let mut graph = this.borrow_mut(&guard);
+        let result = graph.apply_batch(&ops);
+        (result, graph.take_events(), graph.listeners())
+      };
+
+      match result {
+        Ok(()) => {
+          dispatch_events(events, listeners);
+          Ok(cx.undefined().upcast())
+        },
+        Err(err) => cx.throw_error(err),
+      }
+    }
+
+    method findCycles(mut cx) {
+      let mut this = cx.this();
+      let edge_type = cx.argument_opt(0).and_then(|edge_type| {
+        if let Ok(_) = edge_type.downcast::<JsNull>() {
+          return None
+        } else if let Ok(_) = edge_type.downcast::<JsUndefined>() {
+          return None
+        }
+        Some(edge_type.downcast::<JsString>().or_throw(&mut cx).unwrap().value())
+      });
+
+      let cycles = {
+        let guard = cx.lock();
+        let graph = this.borrow_mut(&guard);
+        graph.find_cycles(edge_type.as_ref().map(|e| &e[..]))
+      };
+
+      let js_cycles = JsArray::new(&mut cx, cycles.len() as u32);
+      for (i, cycle) in cycles.iter().enumerate() {
+        let js_cycle = JsArray::new(&mut cx, cycle.len() as u32);
+        for (j, node) in cycle.iter().enumerate() {
+          let js_node = value_to_js_value(&mut cx, &Value::Object(node.clone()))?;
+          js_cycle.set(&mut cx, j as u32, js_node)?;
+        }
+        js_cycles.set(&mut cx, i as u32, js_cycle)?;
+      }
+      Ok(js_cycles.upcast())
+    }
+
+    method topologicalSort(mut cx) {
+      let mut this = cx.this();
+      let edge_type = cx.argument_opt(0).and_then(|edge_type| {
+        if let Ok(_) = edge_type.downcast::<JsNull>() {
+          return None
+        } else if let Ok(_) = edge_type.downcast::<JsUndefined>() {
+          return None
+        }
+        Some(edge_type.downcast::<JsString>().or_throw(&mut cx).unwrap().value())
+      });
+
+      let result = {
+        let guard = cx.lock();
+        let graph = this.borrow_mut(&guard);
+        graph.topological_sort(edge_type.as_ref().map(|e| &e[..]))
+      };
+
+      match result {
+        Ok(nodes) => {
+          let js_array = JsArray::new(&mut cx, nodes.len() as u32);
+          for (i, node) in nodes.iter().enumerate() {
+            let js_node = value_to_js_value(&mut cx, &Value::Object(node.clone()))?;
+            js_array.set(&mut cx, i as u32, js_node)?;
+          }
+          Ok(js_array.upcast())
+        },
+        Err(_cycles) => cx.throw_error("Graph contains a cycle; no topological order exists"),
+      }
+    }
   }
 }
 